@@ -0,0 +1,285 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! A small templating layer on top of [`KeyExpr`](super::KeyExpr) that lets you describe a
+//! family of key expressions with named, typed fields, then either destructure a concrete
+//! [`keyexpr`] against that description or reconstruct a concrete [`KeyExpr`] from field values.
+//!
+//! ```rust
+//! # use std::convert::TryFrom;
+//! # use zenoh::prelude::keyexpr;
+//! # use zenoh::key_expr::format::KeFormat;
+//! let format = KeFormat::new("robot/${id:*}/sensor/${kind:**}").unwrap();
+//! let parsed = format.parse(keyexpr::new("robot/1/sensor/imu/accel").unwrap()).unwrap();
+//! assert_eq!(parsed.get("id"), Some("1"));
+//! assert_eq!(parsed.get("kind"), Some("imu/accel"));
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use zenoh_core::Result as ZResult;
+pub use zenoh_protocol_core::key_expr::*;
+
+use super::KeyExpr;
+
+/// The wildcard a [`KeFormat`] field is allowed to bind to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Wildcard {
+    /// `*`: exactly one chunk.
+    Single,
+    /// `**`: any number of chunks. The literal segments around a field carry its `/`
+    /// separators, so a `**` field that sits between two `/` (as in `a/${x:**}/b`) binds at
+    /// least one chunk: `a/b` does not match that format, and formatting it with an empty value
+    /// fails.
+    Multi,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Field {
+    name: Box<str>,
+    wildcard: Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(Box<str>),
+    Field(Field),
+}
+
+/// A parsed key-expression format, such as `robot/${id:*}/sensor/${kind:**}`.
+///
+/// A [`KeFormat`] alternates literal segments with named, wildcarded fields. Use
+/// [`KeFormat::parse`] to destructure a concrete [`keyexpr`] against it, or [`KeFormat::format`]
+/// to reconstruct a concrete [`KeyExpr`] from a set of field values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeFormat {
+    segments: Vec<Segment>,
+}
+
+impl KeFormat {
+    /// Parses `format` into a [`KeFormat`], validating that fields are well formed and that no
+    /// two wildcarded fields are adjacent without a literal separator between them (which would
+    /// make them impossible to tell apart, the same hazard [`KeyExpr::concat`](super::KeyExpr::concat) already guards against).
+    pub fn new<S: AsRef<str> + ?Sized>(format: &S) -> ZResult<Self> {
+        let format = format.as_ref();
+        let mut segments = Vec::new();
+        let mut rest = format;
+        let mut last_was_field = false;
+        while let Some(start) = rest.find("${") {
+            let literal = &rest[..start];
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(literal.into()));
+                last_was_field = false;
+            } else if last_was_field {
+                bail!(
+                    "Key expression format {} has two wildcard fields with no literal separator between them, which would make them impossible to tell apart",
+                    format
+                )
+            }
+            let after_open = &rest[start + 2..];
+            let end = after_open
+                .find('}')
+                .ok_or_else(|| zenoh_core::zerror!("Unterminated field in key expression format {}", format))?;
+            let field = &after_open[..end];
+            let (name, wildcard) = field
+                .split_once(':')
+                .ok_or_else(|| zenoh_core::zerror!("Field {{{}}} in key expression format {} is missing a `:wildcard` spec", field, format))?;
+            if name.is_empty() {
+                bail!("Field {{{}}} in key expression format {} has an empty name", field, format)
+            }
+            let wildcard = match wildcard {
+                "*" => Wildcard::Single,
+                "**" => Wildcard::Multi,
+                _ => bail!(
+                    "Field {{{}}} in key expression format {} has unsupported wildcard `{}` (expected `*` or `**`)",
+                    field, format, wildcard
+                ),
+            };
+            segments.push(Segment::Field(Field { name: name.into(), wildcard }));
+            last_was_field = true;
+            rest = &after_open[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.into()));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Matches `input` against this format, extracting the value bound to each field.
+    ///
+    /// Fails if `input` doesn't fit the format's shape: literal anchors must match exactly, `*`
+    /// fields bind to exactly one chunk, and `**` fields bind to the minimal span of chunks
+    /// needed for the next literal anchor to align.
+    pub fn parse<'s, 'k>(&'s self, input: &'k keyexpr) -> ZResult<Parsed<'s, 'k>> {
+        let input = input.as_str();
+        let mut bindings = Vec::new();
+        let mut pos = 0;
+        let mut segments = self.segments.iter().peekable();
+        while let Some(segment) = segments.next() {
+            match segment {
+                Segment::Literal(literal) => {
+                    if !input[pos..].starts_with(literal.as_ref()) {
+                        bail!("{} does not match format (expected `{}` at byte {})", input, literal, pos)
+                    }
+                    pos += literal.len();
+                }
+                Segment::Field(field) => match field.wildcard {
+                    Wildcard::Single => {
+                        let chunk_end = input[pos..].find('/').map_or(input.len(), |i| pos + i);
+                        if chunk_end == pos {
+                            bail!("{} does not match format (field `{}` must bind to a non-empty chunk)", input, field.name)
+                        }
+                        bindings.push((field.name.as_ref(), &input[pos..chunk_end]));
+                        pos = chunk_end;
+                    }
+                    Wildcard::Multi => {
+                        let next_literal = match segments.peek() {
+                            Some(Segment::Literal(literal)) => Some(literal.as_ref()),
+                            Some(Segment::Field(_)) => unreachable!("two adjacent fields are rejected at parse time"),
+                            None => None,
+                        };
+                        let span_end = match next_literal {
+                            None => input.len(),
+                            Some(anchor) => find_chunk_aligned(&input[pos..], anchor)
+                                .ok_or_else(|| zenoh_core::zerror!("{} does not match format (couldn't find anchor `{}` after field `{}`)", input, anchor, field.name))?
+                                + pos,
+                        };
+                        bindings.push((field.name.as_ref(), &input[pos..span_end]));
+                        pos = span_end;
+                    }
+                },
+            }
+        }
+        if pos != input.len() {
+            bail!("{} does not match format (trailing `{}` left unmatched)", input, &input[pos..])
+        }
+        Ok(Parsed { bindings })
+    }
+
+    /// Substitutes each field with its value from `values`, returning the resulting canonical
+    /// [`KeyExpr`].
+    ///
+    /// Fails if a field has no corresponding value, if a `*` field's value contains a `/`, or if
+    /// a value contains a `*`: values are meant to be concrete, so a caller-supplied `*`/`**`
+    /// must never be allowed to turn into a live wildcard in the resulting key expression.
+    pub fn format(&self, values: &HashMap<&str, &str>) -> ZResult<KeyExpr<'static>> {
+        let mut result = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => result.push_str(literal),
+                Segment::Field(field) => {
+                    let value = values
+                        .get(field.name.as_ref())
+                        .ok_or_else(|| zenoh_core::zerror!("Missing value for field `{}`", field.name))?;
+                    if value.contains('*') {
+                        bail!("Field `{}`'s value `{}` contains a `*`, which would turn into a wildcard in the resulting key expression", field.name, value)
+                    }
+                    if field.wildcard == Wildcard::Single && value.contains('/') {
+                        bail!("Field `{}` expects a single chunk, but value `{}` contains a `/`", field.name, value)
+                    }
+                    result.push_str(value);
+                }
+            }
+        }
+        KeyExpr::try_from(result)
+    }
+}
+
+/// Finds the earliest position in `input` at which `anchor` starts on a chunk boundary (i.e. at
+/// the very start of `input`, or right at a `/`; the anchor itself, being the literal segment
+/// that follows a `**` field, carries that separating `/` as its own leading character).
+fn find_chunk_aligned(input: &str, anchor: &str) -> Option<usize> {
+    if input.starts_with(anchor) {
+        return Some(0);
+    }
+    let mut search_from = 0;
+    while let Some(slash) = input[search_from..].find('/') {
+        let chunk_boundary = search_from + slash;
+        if input[chunk_boundary..].starts_with(anchor) {
+            return Some(chunk_boundary);
+        }
+        search_from = chunk_boundary + 1;
+    }
+    None
+}
+
+/// The result of matching a [`KeFormat`] against a concrete [`keyexpr`], giving access to the
+/// value bound to each named field.
+#[derive(Debug, Clone)]
+pub struct Parsed<'s, 'k> {
+    bindings: Vec<(&'s str, &'k str)>,
+}
+impl<'s, 'k> Parsed<'s, 'k> {
+    /// Returns the value bound to the field named `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&'k str> {
+        self.bindings
+            .iter()
+            .find_map(|(field, value)| (*field == name).then_some(*value))
+    }
+}
+
+#[test]
+fn parse_and_format_roundtrip() {
+    use std::convert::TryInto;
+    let format = KeFormat::new("robot/${id:*}/sensor/${kind:**}").unwrap();
+    let ke: OwnedKeyExpr = "robot/1/sensor/imu/accel".try_into().unwrap();
+    let parsed = format.parse(&ke).unwrap();
+    assert_eq!(parsed.get("id"), Some("1"));
+    assert_eq!(parsed.get("kind"), Some("imu/accel"));
+
+    let mut values = HashMap::new();
+    values.insert("id", "1");
+    values.insert("kind", "imu/accel");
+    let rebuilt = format.format(&values).unwrap();
+    assert_eq!(rebuilt.as_str(), "robot/1/sensor/imu/accel");
+}
+
+#[test]
+fn multi_field_followed_by_literal() {
+    use std::convert::TryInto;
+    let format = KeFormat::new("a/${x:**}/b").unwrap();
+
+    let ke: OwnedKeyExpr = "a/m/n/b".try_into().unwrap();
+    assert_eq!(format.parse(&ke).unwrap().get("x"), Some("m/n"));
+
+    let ke: OwnedKeyExpr = "a/m/b".try_into().unwrap();
+    assert_eq!(format.parse(&ke).unwrap().get("x"), Some("m"));
+}
+
+#[test]
+fn single_field_rejects_slash() {
+    let format = KeFormat::new("a/${x:*}").unwrap();
+    let mut values = HashMap::new();
+    values.insert("x", "b/c");
+    assert!(format.format(&values).is_err());
+}
+
+#[test]
+fn field_value_rejects_wildcard() {
+    let mut values = HashMap::new();
+
+    let single = KeFormat::new("a/${x:*}").unwrap();
+    values.insert("x", "*");
+    assert!(single.format(&values).is_err());
+
+    let multi = KeFormat::new("a/${x:**}").unwrap();
+    values.insert("x", "b/**");
+    assert!(multi.format(&values).is_err());
+}
+
+#[test]
+fn adjacent_wildcards_rejected() {
+    assert!(KeFormat::new("${a:*}${b:*}").is_err());
+}
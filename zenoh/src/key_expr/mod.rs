@@ -21,6 +21,8 @@ pub use zenoh_protocol_core::key_expr::*;
 
 use crate::Session;
 
+pub mod format;
+
 #[derive(Clone)]
 pub(crate) enum KeyExprInner<'a> {
     Borrowed(&'a keyexpr),
@@ -147,6 +149,27 @@ impl std::hash::Hash for KeyExpr<'_> {
         self.as_keyexpr().hash(state);
     }
 }
+#[cfg(feature = "serde")]
+impl serde::Serialize for KeyExpr<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for KeyExpr<'static> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        // Always goes through validation, and always yields an `Owned` key expression: the
+        // `Wire`/`BorrowedWire` optimization state is session-scoped and meaningless off-wire.
+        KeyExpr::try_from(s).map_err(serde::de::Error::custom)
+    }
+}
 
 impl KeyExpr<'static> {
     /// Constructs an [`KeyExpr`] without checking [`keyexpr`]'s invariants
@@ -307,6 +330,45 @@ impl<'a> KeyExpr<'a> {
             Ok(r.into())
         }
     }
+
+    /// If `prefix` is a literal ancestor of `self` (i.e. `self` is `prefix` followed by a `/` and
+    /// at least one more chunk), returns the rest of `self` past that prefix. Returns `None` if
+    /// `prefix` isn't a literal ancestor of `self`, including when `prefix` itself contains a
+    /// wildcard, as stripping across a wildcard boundary is never well-defined.
+    ///
+    /// This is notably useful for workspaces: the same use-case [`KeyExpr::join`] documents, but
+    /// in reverse, to turn an absolute key back into a workspace-relative one.
+    ///
+    /// The result always degrades to a plain owned key expression: `self`'s `expr_id`, if any,
+    /// was declared against `self`'s own bytes, and the wire encoding sends `key_expr[prefix_len..]`
+    /// as the suffix, so reusing that `expr_id` after bytes have been removed from the *front* of
+    /// the key would make a remote peer resolve the wrong resource.
+    pub fn strip_prefix(&self, prefix: &keyexpr) -> Option<KeyExpr<'static>> {
+        if prefix.as_str().contains('*') {
+            return None;
+        }
+        let self_str = self.as_str();
+        let prefix_str = prefix.as_str();
+        if self_str.len() <= prefix_str.len()
+            || !self_str.starts_with(prefix_str)
+            || self_str.as_bytes()[prefix_str.len()] != b'/'
+        {
+            return None;
+        }
+        let strip_len = prefix_str.len() + 1;
+        let rest = OwnedKeyExpr::try_from(&self_str[strip_len..]).ok()?;
+        Some(rest.into())
+    }
+
+    /// Rebases `self` from `old_prefix` to `new_prefix`: strips `old_prefix` off `self`, then
+    /// joins the remainder onto `new_prefix`.
+    ///
+    /// Returns `None` under the same conditions as [`KeyExpr::strip_prefix`], and also when
+    /// joining the remainder onto `new_prefix` doesn't yield a valid key expression.
+    pub fn rebase(&self, old_prefix: &keyexpr, new_prefix: &keyexpr) -> Option<KeyExpr<'static>> {
+        let relative = self.strip_prefix(old_prefix)?;
+        KeyExpr::try_from(format!("{}/{}", new_prefix, relative)).ok()
+    }
 }
 
 impl<'a> KeyExpr<'a> {
@@ -365,3 +427,63 @@ fn size_of_KeyExpr() {
         4 * std::mem::size_of::<usize>()
     );
 }
+
+#[test]
+fn strip_prefix_always_degrades_to_owned() {
+    // Even when the stripped prefix lies entirely within the already-optimized `prefix_len`
+    // region, `expr_id` was declared against `self`'s own bytes: reusing it after bytes have
+    // been removed from the front of the key would make a remote peer resolve the wrong
+    // resource, so the result must always be a plain `Owned` key expression.
+    let key_expr: OwnedKeyExpr = "a/b/c/d".try_into().unwrap();
+    let wire = KeyExpr(KeyExprInner::Wire {
+        key_expr,
+        expr_id: 42,
+        prefix_len: 6, // "a/b/c/" is already known to the session
+        session_id: 7,
+    });
+    let stripped = wire.strip_prefix(keyexpr::new("a/b").unwrap()).unwrap();
+    assert_eq!(stripped.as_str(), "c/d");
+    assert!(matches!(stripped.0, KeyExprInner::Owned(_)));
+}
+
+#[test]
+fn strip_prefix_rejects_wildcard_prefix() {
+    let ke: KeyExpr = keyexpr::new("a/b/c").unwrap().into();
+    assert!(ke.strip_prefix(keyexpr::new("a/*").unwrap()).is_none());
+}
+
+#[test]
+fn strip_prefix_rejects_non_ancestor() {
+    let ke: KeyExpr = keyexpr::new("a/b/c").unwrap().into();
+    assert!(ke.strip_prefix(keyexpr::new("x/y").unwrap()).is_none());
+    assert!(ke.strip_prefix(keyexpr::new("a/bc").unwrap()).is_none());
+    assert!(ke.strip_prefix(keyexpr::new("a/b/c").unwrap()).is_none());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_roundtrip() {
+    let ke: KeyExpr = keyexpr::new("a/b/c").unwrap().into();
+    let json = serde_json::to_string(&ke).unwrap();
+    assert_eq!(json, "\"a/b/c\"");
+    let de: KeyExpr = serde_json::from_str(&json).unwrap();
+    assert_eq!(de, ke);
+    assert!(matches!(de.0, KeyExprInner::Owned(_)));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_deserialize_rejects_invalid_key_expr() {
+    // An empty string is never a valid `keyexpr`, so this must fail to deserialize rather than
+    // produce an unchecked `Owned` key expression.
+    assert!(serde_json::from_str::<KeyExpr>("\"\"").is_err());
+}
+
+#[test]
+fn rebase_moves_between_workspaces() {
+    let ke: KeyExpr = keyexpr::new("old/a/b").unwrap().into();
+    let rebased = ke
+        .rebase(keyexpr::new("old").unwrap(), keyexpr::new("new").unwrap())
+        .unwrap();
+    assert_eq!(rebased.as_str(), "new/a/b");
+}